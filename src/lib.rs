@@ -1,83 +1,143 @@
 #![allow(dead_code)]
-#![feature(const_fn)]
 //#![no_std]
 extern crate core;
+#[cfg(not(feature = "std"))]
+extern crate core_io;
 
 use core::cmp;
 //use core::mem;
-use core::marker::PhantomData;
-use core::cell::Cell;
-
-macro_rules! impl_byte_array_recursive {
-    ($($size:expr),*) => {
-        $(
-            impl_byte_array!($size);
-        )*
-             
-    }
-}
-
-macro_rules! impl_byte_array {
-    ($size:expr) => {
-        impl ByteArray for [u8; $size] {
-            fn get(&mut self, index: usize) -> u8 {
-                self[index]
-            }
-            fn set(&mut self, index: usize, value: u8) {
-                self[index] = value
-            }
+use core::ptr;
+use core::slice;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+// Declares a backing array with `'static` storage and a `RingBuf` already
+// `init`-ed onto it, and hands back its reader/writer halves. Equivalent to
+// writing `static RING: RingBuf<T> = RingBuf::new();` followed by a runtime
+// `RING.init(&mut BUF)`, just spelled as one line for tests and small
+// drivers that don't need to name the ring themselves.
+macro_rules! ring_buf {
+    ($ty:ty, $fill:expr, $size:expr) => {
+        {
+            static mut BUF: [$ty; $size] = [$fill; $size];
+            static RBUF: RingBuf<$ty> = RingBuf::new();
+            unsafe { RBUF.init(&mut BUF); }
+            (RBUF.reader(), RBUF.writer())
         }
     }
 }
 
-impl_byte_array_recursive!(1, 2, 4, 8, 16, 32, 64, 128, 256, 512, 1024, 2048, 4096);
+// What `enqueue`/`write` do when the ring is full. `Reject` is the
+// traditional FIFO behavior; `Overwrite` is the trace/console-buffer
+// behavior where the newest data matters most, so the oldest byte is
+// dropped to make room instead of refusing the new one.
+//
+// `Overwrite` is only sound with a single-threaded ring, or from the
+// producer side while the consumer is known to be idle: evicting a slot
+// races with a consumer that has already loaded the old `reader` value and
+// is mid-read of `buffer` at that index, no matter how `reader` itself is
+// advanced.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum OverflowMode {
+    Reject,
+    Overwrite,
+}
 
-macro_rules! static_ring_buf {
-    ($size:expr) => {
-        {
-            static mut RBUF: RingBuf = RingBuf { reader: Cell::new(0), writer: Cell::new(0), length: $size, buffer: &mut [0u8; $size] as *mut ByteArray};
-            unsafe { (RBUF.reader(), RBUF.writer() )}
+impl OverflowMode {
+    fn to_usize(self) -> usize {
+        match self {
+            OverflowMode::Reject => 0,
+            OverflowMode::Overwrite => 1,
         }
     }
-}
 
-macro_rules! ring_buf {
-    ($size:expr) => {
-        {
-            let mut rbuf = RingBuf { reader: Cell::new(0), writer: Cell::new(0), length: $size, buffer: &mut [0u8; $size] as *mut ByteArray};
-            (rbuf.reader(), rbuf.writer())
+    fn from_usize(value: usize) -> OverflowMode {
+        match value {
+            1 => OverflowMode::Overwrite,
+            _ => OverflowMode::Reject,
         }
     }
 }
 
-pub trait ByteArray {
-    fn get(&mut self, index: usize) -> u8;
-    fn set(&mut self, index: usize, value: u8);
+pub struct RingBuf<T> {
+    reader: AtomicUsize,
+    writer: AtomicUsize,
+    length: AtomicUsize,
+    buffer: AtomicPtr<T>,
+    mode: AtomicUsize,
+    dropped: AtomicUsize,
 }
 
+impl<T> RingBuf<T> {
+    // No backing storage and zero capacity, so it can initialize a `static`
+    // before real memory for it exists, e.g.
+    // `static RING: RingBuf<u8> = RingBuf::new();`. Attach storage
+    // afterwards with `init`. Starts in `OverflowMode::Reject`.
+    pub const fn new() -> RingBuf<T> {
+        RingBuf {
+            reader: AtomicUsize::new(0),
+            writer: AtomicUsize::new(0),
+            length: AtomicUsize::new(0),
+            buffer: AtomicPtr::new(ptr::null_mut()),
+            mode: AtomicUsize::new(0),
+            dropped: AtomicUsize::new(0),
+        }
+    }
 
-pub struct RingBuf {
-    reader: Cell<usize>,
-    writer: Cell<usize>,
-    length: usize,
-    buffer: *mut ByteArray,
-}
+    // Switches between rejecting new data and overwriting the oldest data
+    // once the ring is full.
+    pub fn set_overflow_mode(&self, mode: OverflowMode) {
+        self.mode.store(mode.to_usize(), Ordering::Relaxed);
+    }
+
+    fn overflow_mode(&self) -> OverflowMode {
+        OverflowMode::from_usize(self.mode.load(Ordering::Relaxed))
+    }
 
-impl RingBuf {
-    pub fn reader<'a>(&'a mut self) -> RingReader<RingBuf> {
-        RingReader { ring: self, _phantom: PhantomData }
+    // Count of elements dropped by `OverflowMode::Overwrite` to make room
+    // for newer data. `OverflowMode::Reject` never adds to it; it is not
+    // reset by a mode switch or by `init`/`deinit`.
+    pub fn overruns(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
     }
 
-    pub fn writer(&mut self) -> RingWriter<RingBuf> {
-        RingWriter { ring: self, _phantom: PhantomData }
-    }    
+    // Attaches backing storage to the ring and resets `reader`/`writer` to
+    // 0. The storage must be `'static` since a `RingReader`/`RingWriter`
+    // created afterwards can freely outlive the scope `init` was called
+    // from (e.g. an interrupt handler bound at startup).
+    pub fn init(&self, buf: &'static mut [T]) {
+        self.length.store(buf.len(), Ordering::Release);
+        self.buffer.store(buf.as_mut_ptr(), Ordering::Release);
+        self.reader.store(0, Ordering::Relaxed);
+        self.writer.store(0, Ordering::Relaxed);
+    }
+
+    // Detaches backing storage and resets `reader`/`writer` to 0, so the
+    // ring can later be `init`-ed onto different storage. The overflow mode
+    // and the `overruns()` count are kept as they are.
+    pub fn deinit(&self) {
+        self.buffer.store(ptr::null_mut(), Ordering::Release);
+        self.length.store(0, Ordering::Release);
+        self.reader.store(0, Ordering::Relaxed);
+        self.writer.store(0, Ordering::Relaxed);
+    }
+
+    pub fn reader(&self) -> RingReader<T> {
+        RingReader { ring: self as *const RingBuf<T> as *mut RingBuf<T> }
+    }
+
+    pub fn writer(&self) -> RingWriter<T> {
+        RingWriter { ring: self as *const RingBuf<T> as *mut RingBuf<T> }
+    }
 
     fn cap(&self) -> usize {
-        self.length
+        self.length.load(Ordering::Acquire)
     }
 
+    // `len` is derived from free-running, monotonically increasing indices
+    // (only masked down to a physical slot in `phy`), so a full ring and an
+    // empty ring never collide on the same reader/writer pair.
     fn len(&self) -> usize {
-        self.writer.get().wrapping_sub(self.reader.get())
+        self.writer.load(Ordering::Acquire).wrapping_sub(self.reader.load(Ordering::Acquire))
     }
 
     fn rem(&self) -> usize {
@@ -85,58 +145,111 @@ impl RingBuf {
     }
 
     fn is_empty(&self) -> bool {
-        self.reader.get() == self.writer.get()
+        self.reader.load(Ordering::Acquire) == self.writer.load(Ordering::Acquire)
     }
 
     fn is_full(&self) -> bool {
         self.len() == self.cap()
     }
 
+    // Bumped only by the consumer. `Release` publishes the freed slot(s) so
+    // a producer on another core/interrupt priority that later does an
+    // `Acquire` load of `reader` is guaranteed to see it.
     fn incr_reader(&self) {
         assert!(!self.is_empty(), "Attempted to increment empty reader");
-        self.reader.set(self.reader.get().wrapping_add(1));
+        self.reader.fetch_add(1, Ordering::Release);
     }
 
-    fn incr_writer(&self) {        
-        assert!(!self.is_full(), "Attempted to increment full writer");
-        self.writer.set(self.writer.get().wrapping_add(1));     
+    // Bumped only by the producer, and only *after* the value(s) have been
+    // written into `buffer` (see `enqueue`/`commit`). `Release` here is what
+    // makes those prior writes visible to a consumer's `Acquire` load of
+    // `writer`. No longer asserts the ring isn't full: `enqueue` already
+    // makes room via the reader-advance path in `OverflowMode::Overwrite`.
+    fn incr_writer(&self) {
+        self.writer.fetch_add(1, Ordering::Release);
     }
 
     fn phy(&self, index: usize) -> usize {
         index % self.cap()
     }
 
-    fn enqueue(&self, value: u8) -> bool {
-        if self.is_full() {
-            false
-        } else {
-            let writer = self.phy(self.writer.get());
-            unsafe { (&mut *self.buffer).set(writer, value); }
-            self.incr_writer();
-            true
+    fn enqueue(&self, value: T) -> bool {
+        if self.cap() == 0 {
+            // Nothing to evict and nowhere to write: a ring with no backing
+            // storage (fresh from `new()`, or `deinit`-ed) never accepts
+            // anything, in either overflow mode. Bail out before `phy()`
+            // divides by the zero capacity below.
+            return false;
         }
+        while self.is_full() {
+            match self.overflow_mode() {
+                OverflowMode::Reject => return false,
+                OverflowMode::Overwrite => {
+                    // Drop the oldest element to make room for this one.
+                    // CAS rather than a blind `fetch_add`: if a concurrent
+                    // consumer has already advanced `reader` (it drained a
+                    // slot itself), re-check `is_full` instead of evicting
+                    // a second time on top of that.
+                    //
+                    // This still only *counts* correctly under concurrency;
+                    // it does not make `Overwrite` safe against a live
+                    // consumer. Evicting a slot races with a consumer that
+                    // already loaded the old `reader` value and is mid-read
+                    // of `buffer` at that index, regardless of how `reader`
+                    // itself is advanced. `Overwrite` is only sound either
+                    // single-threaded, or from the producer side while the
+                    // consumer is known to be idle.
+                    let reader = self.reader.load(Ordering::Acquire);
+                    if self.reader.compare_exchange(reader, reader.wrapping_add(1), Ordering::Release, Ordering::Relaxed).is_ok() {
+                        self.dropped.fetch_add(1, Ordering::Relaxed);
+                        break;
+                    }
+                }
+            }
+        }
+        let writer = self.phy(self.writer.load(Ordering::Relaxed));
+        unsafe { *self.buffer.load(Ordering::Relaxed).add(writer) = value; }
+        self.incr_writer();
+        true
     }
 
-    fn dequeue(&self) -> Option<u8> {
+    fn dequeue(&self) -> Option<T>
+    where
+        T: Copy,
+    {
         if self.is_empty() {
             None
         } else {
-            let reader = self.phy(self.reader.get());
-            let value = unsafe { (&mut *self.buffer).get(reader) };
+            let reader = self.phy(self.reader.load(Ordering::Relaxed));
+            let value = unsafe { *self.buffer.load(Ordering::Relaxed).add(reader) };
             self.incr_reader();
             Some(value)
         }
     }
 
-    fn write(&self, buf: &[u8]) -> usize {
-        let n = cmp::min(self.rem(), buf.len());
+    fn write(&self, buf: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        // In `OverflowMode::Overwrite` the whole slice is always accepted,
+        // dropping the oldest elements as needed; `Reject` still truncates
+        // to the available space. A ring with no backing storage accepts
+        // nothing in either mode.
+        let n = match self.overflow_mode() {
+            OverflowMode::Reject => cmp::min(self.rem(), buf.len()),
+            OverflowMode::Overwrite if self.cap() == 0 => 0,
+            OverflowMode::Overwrite => buf.len(),
+        };
         for i in 0..n {
             self.enqueue(buf[i]);
         }
         n
     }
 
-    fn read(&self, buf: &mut [u8]) -> usize {
+    fn read(&self, buf: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
         let n = cmp::min(self.len(), buf.len());
         for i in 0..n {
             buf[i] = self.dequeue().expect("Ring buffer is empty");
@@ -144,55 +257,226 @@ impl RingBuf {
         n
     }
 
+    // Largest *contiguous* writable run, starting at the physical write
+    // cursor: either up to the physical end of the backing array or up to
+    // `rem()`, whichever is smaller. A peripheral doing DMA writes directly
+    // into this slice and then calls `commit` to publish what it wrote. If
+    // the free region wraps past the end of the buffer, call this again
+    // after `commit` to get the chunk that continues from the start.
+    //
+    // Takes `&mut self` (unlike the rest of this lock-free API) because the
+    // returned `&mut [T]` aliases the backing storage: a second call before
+    // `commit` would otherwise hand out two live `&mut` slices over the same
+    // memory, which is UB regardless of whether anyone's racing concurrently.
+    // Requiring a unique borrow of the writer for the slice's lifetime is
+    // the only way to make that a compile error instead.
+    fn get_unallocated(&mut self) -> &mut [T] {
+        if self.cap() == 0 {
+            return &mut [];
+        }
+        let phy = self.phy(self.writer.load(Ordering::Relaxed));
+        let n = cmp::min(self.rem(), self.cap() - phy);
+        unsafe { slice::from_raw_parts_mut(self.buffer.load(Ordering::Relaxed).add(phy), n) }
+    }
+
+    // Publishes `n` elements written into the slice returned by the most
+    // recent `get_unallocated` call, advancing `writer` past them. Bounded
+    // by that slice's own length (not just the ring's total free space):
+    // committing past it would walk past the physical end of the buffer
+    // into the wrapped region's existing data, silently corrupting the
+    // stream instead of the DMA overrunning into the next contiguous chunk.
+    fn commit(&self, n: usize) {
+        if self.cap() == 0 {
+            assert_eq!(n, 0, "Attempted to commit on a ring with no backing storage");
+            return;
+        }
+        let phy = self.phy(self.writer.load(Ordering::Relaxed));
+        assert!(n <= cmp::min(self.rem(), self.cap() - phy), "Attempted to commit past the end of the unallocated region");
+        self.writer.fetch_add(n, Ordering::Release);
+    }
+
+    // Largest *contiguous* readable run, starting at the physical read
+    // cursor, mirroring `get_unallocated`. A peripheral doing DMA reads
+    // directly from this slice and then calls `dequeue_many` to release it.
+    // Returns `&[T]` rather than `&mut [T]`, so (unlike `get_unallocated`)
+    // aliasing two calls is just two shared borrows — no `&mut self` needed.
+    fn get_allocated(&self) -> &[T] {
+        if self.cap() == 0 {
+            return &[];
+        }
+        let phy = self.phy(self.reader.load(Ordering::Relaxed));
+        let n = cmp::min(self.len(), self.cap() - phy);
+        unsafe { slice::from_raw_parts(self.buffer.load(Ordering::Relaxed).add(phy), n) }
+    }
+
+    // Releases `n` elements read from the slice returned by the most recent
+    // `get_allocated` call, advancing `reader` past them. Bounded by that
+    // slice's own length for the same reason as `commit`.
+    fn dequeue_many(&self, n: usize) {
+        if self.cap() == 0 {
+            assert_eq!(n, 0, "Attempted to dequeue_many on a ring with no backing storage");
+            return;
+        }
+        let phy = self.phy(self.reader.load(Ordering::Relaxed));
+        assert!(n <= cmp::min(self.len(), self.cap() - phy), "Attempted to dequeue past the end of the allocated region");
+        self.reader.fetch_add(n, Ordering::Release);
+    }
+
 }
 
 pub struct RingReader<T> {
-    ring: *mut RingBuf,
-    _phantom: PhantomData<T>
+    ring: *mut RingBuf<T>,
 }
 
+// Safe to hand to a single consumer (e.g. the main loop) while a `RingWriter`
+// lives on the other side (e.g. an interrupt handler): all shared state is
+// atomic and `enqueue`/`dequeue` only take `&self`. Bounded on `T: Send`
+// since values of `T` themselves cross that boundary through the ring, same
+// as `std::sync::mpsc::Receiver<T>` requires.
+unsafe impl<T: Send> Send for RingReader<T> {}
+
 impl<T> RingReader<T> {
-    pub fn dequeue(&mut self) -> Option<u8> {
-        let ring = unsafe { &mut *self.ring};
+    pub fn dequeue(&self) -> Option<T>
+    where
+        T: Copy,
+    {
+        let ring = unsafe { &*self.ring };
         ring.dequeue()
     }
 
-    pub fn read(&mut self, buf: &mut [u8]) -> usize {
-        let ring = unsafe { &mut *self.ring};
+    pub fn read(&self, buf: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let ring = unsafe { &*self.ring };
         ring.read(buf)
     }
+
+    pub fn get_allocated(&self) -> &[T] {
+        let ring = unsafe { &*self.ring };
+        ring.get_allocated()
+    }
+
+    pub fn dequeue_many(&self, n: usize) {
+        let ring = unsafe { &*self.ring };
+        ring.dequeue_many(n)
+    }
+
+    pub fn overruns(&self) -> usize {
+        let ring = unsafe { &*self.ring };
+        ring.overruns()
+    }
 }
 
 pub struct RingWriter<T> {
-    ring: *mut RingBuf,
-    _phantom: PhantomData<T>
+    ring: *mut RingBuf<T>,
 }
 
+// See `RingReader`'s impl above: bounded on `T: Send` for the same reason.
+unsafe impl<T: Send> Send for RingWriter<T> {}
+
 impl<T> RingWriter<T> {
-    pub fn enqueue(&mut self, value: u8) -> bool {
-        let ring = unsafe { &mut *self.ring};
+    pub fn set_overflow_mode(&self, mode: OverflowMode) {
+        let ring = unsafe { &*self.ring };
+        ring.set_overflow_mode(mode)
+    }
+
+    pub fn overruns(&self) -> usize {
+        let ring = unsafe { &*self.ring };
+        ring.overruns()
+    }
+
+    pub fn enqueue(&self, value: T) -> bool {
+        let ring = unsafe { &*self.ring };
         ring.enqueue(value)
     }
-    pub fn write(&mut self, buf: &[u8]) -> usize {
-        let ring = unsafe { &mut *self.ring};
+    pub fn write(&self, buf: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let ring = unsafe { &*self.ring };
         ring.write(buf)
     }
+
+    // `&mut self`: see the matching comment on `RingBuf::get_unallocated`.
+    // Requiring a unique borrow of the writer itself is what makes calling
+    // this twice before `commit` a compile error instead of aliasing UB.
+    pub fn get_unallocated(&mut self) -> &mut [T] {
+        let ring = unsafe { &mut *self.ring };
+        ring.get_unallocated()
+    }
+
+    pub fn commit(&self, n: usize) {
+        let ring = unsafe { &*self.ring };
+        ring.commit(n)
+    }
 }
 
-#[cfg(test)]
-mod tests {
+// `Read`/`Write` only make sense over a byte ring, so these are implemented
+// for `RingReader<u8>`/`RingWriter<u8>` rather than the generic `T`. The
+// `std` feature picks `std::io`; without it (e.g. building for `no_std`
+// targets) we fall back to the equivalent `core_io` traits instead.
+#[cfg(feature = "std")]
+mod std_io {
     use super::*;
+    use std::io;
+
+    // `self.write(buf)`/`self.read(buf)` here would resolve right back to
+    // this same trait method (the `&mut Self` receiver matches the trait
+    // signature with no adjustment needed, so it doesn't fall back to the
+    // inherent `&self` method the way it would outside the impl) — call the
+    // inherent methods by their fully-qualified path instead.
+    impl io::Write for RingWriter<u8> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(RingWriter::write(self, buf))
+        }
 
-    #[test]
-    fn test_bytearray() {
-        let mut arr = [0u8; 16];
-        arr.set(0, 1);
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
     }
 
+    impl io::Read for RingReader<u8> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            Ok(RingReader::read(self, buf))
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod no_std_io {
+    use super::*;
+    use core_io as io;
+    use core_io::{Read, Write};
+
+    // See the matching comment in `std_io` above: these must call the
+    // inherent `RingWriter::write`/`RingReader::read` by fully-qualified
+    // path to avoid recursing into the trait method being implemented.
+    impl Write for RingWriter<u8> {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            Ok(RingWriter::write(self, buf))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Read for RingReader<u8> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            Ok(RingReader::read(self, buf))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
     #[test]
     fn test_enqueue_dequeue() {
-        let (mut reader, mut writer) = static_ring_buf!(16);
-        
+        let (reader, writer) = ring_buf!(u8, 0u8, 16);
+
         for i in 0..16 {
             assert_eq!(writer.enqueue(i as u8), true);
         }
@@ -202,7 +486,7 @@ mod tests {
     }
     #[test]
     fn test_write_read() {
-        let (mut reader, mut writer) = static_ring_buf!(16);
+        let (reader, writer) = ring_buf!(u8, 0u8, 16);
 
         let src: [u8; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15];
         let mut dst = [0u8; 16];
@@ -218,7 +502,7 @@ mod tests {
     #[test]
     fn test_driver() {
         pub struct Driver {
-            writer: RingWriter<RingBuf>
+            writer: RingWriter<u8>
         }
 
         impl Driver {
@@ -227,7 +511,7 @@ mod tests {
             }
         }
 
-        let (mut reader, writer) = ring_buf!(16);
+        let (reader, writer) = ring_buf!(u8, 0u8, 16);
         let mut d = Driver { writer: writer };
         d.run();
 
@@ -239,7 +523,7 @@ mod tests {
     #[test]
     fn test_static_driver() {
         pub struct Driver {
-            writer: RingWriter<RingBuf>
+            writer: RingWriter<u8>
         }
 
         impl Driver {
@@ -248,8 +532,8 @@ mod tests {
             }
         }
         static mut DRV: Option<Driver> = None;
-        let (mut reader, writer) = ring_buf!(16);
-        {            
+        let (reader, writer) = ring_buf!(u8, 0u8, 16);
+        {
             unsafe {
                 DRV = Some(Driver { writer: writer });
                 &DRV.as_mut().unwrap().run();
@@ -262,9 +546,200 @@ mod tests {
         let mut dst = [0u8; 16];
         let n = reader.read(&mut dst);
         assert_eq!(&dst[..n], b"ABC");
-    }    
+    }
 
     #[test]
     fn test_static() {
     }
+
+    // Exercises the SPSC split: the writer half is moved into a spawned
+    // thread (standing in for an interrupt handler) while the reader half is
+    // kept on the "main loop" side, with no locking between them.
+    #[test]
+    fn test_send_across_threads() {
+        fn assert_send<T: Send>() {}
+        assert_send::<RingWriter<u8>>();
+        assert_send::<RingReader<u8>>();
+
+        let (reader, writer) = ring_buf!(u8, 0u8, 16);
+
+        let handle = std::thread::spawn(move || {
+            writer.write(b"ABC");
+        });
+        handle.join().unwrap();
+
+        let mut dst = [0u8; 16];
+        let n = reader.read(&mut dst);
+        assert_eq!(&dst[..n], b"ABC");
+    }
+
+    // The same enqueue/dequeue round trip as `test_enqueue_dequeue`, but over
+    // a non-`u8` element type, which is the point of parameterizing `RingBuf`.
+    #[test]
+    fn test_generic_element_type() {
+        #[derive(Copy, Clone, Debug, PartialEq)]
+        struct Sample {
+            channel: u8,
+            value: u16,
+        }
+
+        // `ring_buf!` needs a const-evaluable fill expression (it backs a
+        // `static`), so spell out the zero value as a struct literal.
+        let (reader, writer) = ring_buf!(Sample, Sample { channel: 0, value: 0 }, 4);
+        for i in 0..4 {
+            assert_eq!(writer.enqueue(Sample { channel: i, value: i as u16 * 10 }), true);
+        }
+        for i in 0..4 {
+            assert_eq!(reader.dequeue(), Some(Sample { channel: i, value: i as u16 * 10 }));
+        }
+    }
+
+    // DMA-style fill: the peripheral is handed a contiguous slice to write
+    // into directly and `commit`s the count afterward, instead of going
+    // through `enqueue` byte-by-byte.
+    #[test]
+    fn test_get_unallocated_commit() {
+        let (reader, mut writer) = ring_buf!(u8, 0u8, 8);
+
+        {
+            let chunk = writer.get_unallocated();
+            chunk[..3].copy_from_slice(b"ABC");
+        }
+        writer.commit(3);
+
+        let mut dst = [0u8; 3];
+        let n = reader.read(&mut dst);
+        assert_eq!(n, 3);
+        assert_eq!(&dst, b"ABC");
+    }
+
+    // Mirrors `test_get_unallocated_commit` on the read side, and exercises
+    // the wrap case: the contiguous run stops at the physical end of the
+    // buffer, so a caller has to call `get_allocated` twice to drain data
+    // that straddles the wrap point.
+    #[test]
+    fn test_get_allocated_wraps() {
+        let (reader, writer) = ring_buf!(u8, 0u8, 4);
+
+        assert_eq!(writer.write(b"AB"), 2); // writer at 2
+        assert_eq!(reader.read(&mut [0u8; 2]), 2); // reader at 2, ring empty
+        assert_eq!(writer.write(b"CDEF"), 4); // wraps: writer at 6, two physical chunks
+
+        let first = reader.get_allocated();
+        assert_eq!(first, b"CD");
+        reader.dequeue_many(first.len());
+
+        let second = reader.get_allocated();
+        assert_eq!(second, b"EF");
+        reader.dequeue_many(second.len());
+    }
+
+    // `dequeue_many` must reject a count past the *contiguous* allocated
+    // region even though it's still within the ring's total used space —
+    // stepping past it would walk into the wrapped chunk at the start of
+    // the buffer instead of the data immediately after it.
+    #[test]
+    #[should_panic]
+    fn test_dequeue_many_past_contiguous_region() {
+        let (reader, writer) = ring_buf!(u8, 0u8, 4);
+
+        assert_eq!(writer.write(b"AB"), 2);
+        assert_eq!(reader.read(&mut [0u8; 2]), 2);
+        assert_eq!(writer.write(b"CDEF"), 4); // wraps: "CD" then "EF"
+
+        let first = reader.get_allocated();
+        assert_eq!(first, b"CD");
+        reader.dequeue_many(first.len() + 1); // past "CD", into the wrapped "EF"
+    }
+
+    // Drives the ring through `std::io::Write`/`Read` directly, e.g. the way
+    // `write!` would format into it.
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_std_io() {
+        use std::io::{Read, Write};
+
+        let (mut reader, mut writer) = ring_buf!(u8, 0u8, 16);
+
+        write!(writer, "hi {}", 42).unwrap();
+
+        // `Read::read` by fully-qualified path: plain `reader.read(..)`
+        // resolves to the inherent, `usize`-returning `RingReader::read`
+        // rather than the trait method, since method lookup always prefers
+        // an inherent match over a trait one.
+        let mut dst = [0u8; 16];
+        let n: usize = Read::read(&mut reader, &mut dst).unwrap();
+        assert_eq!(&dst[..n], b"hi 42");
+    }
+
+    // `RingBuf::new()` is const, so it can be the initializer of a `static`
+    // declared before its backing storage exists; `init` attaches the
+    // storage afterwards, and `deinit` can detach it and re-point the same
+    // ring at something else later.
+    #[test]
+    fn test_init_deinit() {
+        static mut BUF_A: [u8; 4] = [0u8; 4];
+        static mut BUF_B: [u8; 4] = [0u8; 4];
+        static RING: RingBuf<u8> = RingBuf::new();
+
+        unsafe { RING.init(&mut BUF_A); }
+        let (reader, writer) = (RING.reader(), RING.writer());
+        assert_eq!(writer.write(b"AB"), 2);
+        assert_eq!(reader.read(&mut [0u8; 2]), 2);
+
+        RING.deinit();
+        assert_eq!(writer.enqueue(b'X'), false);
+
+        unsafe { RING.init(&mut BUF_B); }
+        assert_eq!(writer.write(b"CD"), 2);
+        let mut dst = [0u8; 2];
+        assert_eq!(reader.read(&mut dst), 2);
+        assert_eq!(&dst, b"CD");
+    }
+
+    // A ring with no backing storage (fresh from `new()`, or `deinit`-ed)
+    // must reject writes and report empty without dividing by its own
+    // zero capacity in `phy()`.
+    #[test]
+    fn test_zero_capacity_accessors() {
+        let ring: RingBuf<u8> = RingBuf::new();
+        let (reader, mut writer) = (ring.reader(), ring.writer());
+
+        assert_eq!(writer.enqueue(b'X'), false);
+        writer.set_overflow_mode(OverflowMode::Overwrite);
+        assert_eq!(writer.enqueue(b'X'), false);
+
+        assert_eq!(writer.get_unallocated().len(), 0);
+        writer.commit(0);
+        assert_eq!(reader.get_allocated().len(), 0);
+        reader.dequeue_many(0);
+    }
+
+    // `OverflowMode::Reject` is the default: a full ring refuses new bytes
+    // and `overruns()` stays at 0.
+    #[test]
+    fn test_overflow_reject() {
+        let (_reader, writer) = ring_buf!(u8, 0u8, 4);
+
+        assert_eq!(writer.write(b"ABCDE"), 4);
+        assert_eq!(writer.enqueue(b'X'), false);
+        assert_eq!(writer.overruns(), 0);
+    }
+
+    // In `OverflowMode::Overwrite` the newest data always wins: a full ring
+    // drops its oldest byte per byte accepted instead of refusing writes,
+    // and the drops are counted in `overruns()`.
+    #[test]
+    fn test_overflow_overwrite() {
+        let (reader, writer) = ring_buf!(u8, 0u8, 4);
+        writer.set_overflow_mode(OverflowMode::Overwrite);
+
+        assert_eq!(writer.write(b"ABCDEF"), 6);
+        assert_eq!(writer.overruns(), 2);
+
+        let mut dst = [0u8; 4];
+        let n = reader.read(&mut dst);
+        assert_eq!(n, 4);
+        assert_eq!(&dst, b"CDEF");
+    }
 }